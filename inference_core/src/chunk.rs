@@ -0,0 +1,124 @@
+use std::ops::Range;
+
+use crate::embed::Embedding;
+use crate::provider::EmbeddingProvider;
+
+/// A token-bounded slice of a longer document, embedded independently so that whole files can be
+/// searched even though the underlying model truncates anything past its max token length. The
+/// `byte_range` lets callers map a hit back to the original source.
+pub struct Chunk {
+    pub text: String,
+    pub byte_range: Range<usize>,
+    pub embedding: Embedding,
+}
+
+/// Splits `text` into windows of at most `max_tokens` tokens, with `overlap` tokens of
+/// carry-over between consecutive windows, and embeds each window through `provider`.
+///
+/// Windows are computed from `tokenizer`'s offset mapping so that each chunk can be traced back
+/// to a byte range in `text`, mirroring how Zed's semantic index stores a source range alongside
+/// every vector. Embedding is routed through `&dyn EmbeddingProvider` rather than a concrete
+/// `Semantic`, so the same windowing logic works whether the model runs locally
+/// (`LocalOnnxProvider`) or behind an HTTP endpoint (`RemoteHttpProvider`) — only tokenization
+/// (needed to compute offsets) stays local.
+pub async fn chunk_and_embed(
+    tokenizer: &tokenizers::Tokenizer,
+    provider: &dyn EmbeddingProvider,
+    text: &str,
+    max_tokens: usize,
+    overlap: usize,
+) -> anyhow::Result<Vec<Chunk>> {
+    anyhow::ensure!(max_tokens > overlap, "max_tokens must be greater than overlap");
+
+    let encoding = tokenizer
+        .encode(text, false)
+        .map_err(|e| anyhow::anyhow!("tokenizer encode error: {}", e))?;
+
+    let byte_ranges = window_byte_ranges(encoding.get_offsets(), max_tokens, overlap);
+    let texts: Vec<&str> = byte_ranges.iter().map(|range| &text[range.clone()]).collect();
+    let embeddings = provider.embed_batch(&texts).await?;
+
+    Ok(byte_ranges
+        .into_iter()
+        .zip(embeddings)
+        .map(|(byte_range, embedding)| Chunk {
+            text: text[byte_range.clone()].to_string(),
+            byte_range,
+            embedding,
+        })
+        .collect())
+}
+
+/// Walks token `offsets` in windows of at most `max_tokens`, each overlapping the previous by
+/// `overlap` tokens, and maps each window to the byte range it spans in the original string.
+/// Pulled out of `chunk_and_embed` as a pure function so the boundary math (the last partial
+/// window, single-window documents, etc.) can be unit tested without a tokenizer or a model.
+fn window_byte_ranges(offsets: &[(usize, usize)], max_tokens: usize, overlap: usize) -> Vec<Range<usize>> {
+    if offsets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut byte_ranges = Vec::new();
+    let mut start = 0;
+    while start < offsets.len() {
+        let end = (start + max_tokens).min(offsets.len());
+        let byte_start = offsets[start].0;
+        let byte_end = offsets[end - 1].1;
+        byte_ranges.push(byte_start..byte_end);
+
+        if end == offsets.len() {
+            break;
+        }
+        start += max_tokens - overlap;
+    }
+
+    byte_ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets_for(token_count: usize) -> Vec<(usize, usize)> {
+        (0..token_count).map(|i| (i, i + 1)).collect()
+    }
+
+    #[test]
+    fn single_window_when_text_fits() {
+        let offsets = offsets_for(3);
+
+        let ranges = window_byte_ranges(&offsets, 4, 1);
+
+        assert_eq!(ranges, vec![0..3]);
+    }
+
+    #[test]
+    fn windows_overlap_by_the_requested_amount() {
+        // 10 tokens, 4 per window, 1 token of overlap -> windows start at 0, 3, 6. The window
+        // starting at 6 already reaches the last offset, so the walk stops there instead of
+        // emitting a redundant trailing window.
+        let offsets = offsets_for(10);
+
+        let ranges = window_byte_ranges(&offsets, 4, 1);
+
+        assert_eq!(ranges, vec![0..4, 3..7, 6..10]);
+    }
+
+    #[test]
+    fn no_overlap_tiles_windows_back_to_back() {
+        let offsets = offsets_for(9);
+
+        let ranges = window_byte_ranges(&offsets, 3, 0);
+
+        assert_eq!(ranges, vec![0..3, 3..6, 6..9]);
+    }
+
+    #[test]
+    fn empty_text_produces_no_windows() {
+        let offsets: Vec<(usize, usize)> = Vec::new();
+
+        let ranges = window_byte_ranges(&offsets, 4, 1);
+
+        assert!(ranges.is_empty());
+    }
+}
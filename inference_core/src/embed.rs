@@ -1,30 +1,93 @@
 use std::pin::Pin;
 use std::sync::Arc;
 use anyhow::anyhow;
-use ndarray::Axis;
+use ndarray::{Array2, ArrayView3, Axis};
 
 use ort::{
     tensor::{FromArray, InputTensor, OrtOwnedTensor},
     Environment, ExecutionProvider, GraphOptimizationLevel, LoggingLevel, SessionBuilder,
 };
 
+/// Hardware backend to run the ONNX session on.
+///
+/// `Auto` tries the accelerators available on the current platform, in order, falling back to
+/// `Cpu` if none of them register successfully. On machines with a GPU, picking the right backend
+/// can cut embedding latency dramatically for the batch/chunking workloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionBackend {
+    Cpu,
+    Cuda,
+    CoreMl,
+    DirectMl,
+    Auto,
+}
+
+impl ExecutionBackend {
+    /// Execution providers to try, in priority order, with `Cpu` always appended last so the
+    /// session still registers successfully when an accelerator isn't available.
+    fn providers(self) -> Vec<ExecutionProvider> {
+        let mut providers = match self {
+            ExecutionBackend::Cpu => vec![],
+            ExecutionBackend::Cuda => vec![ExecutionProvider::cuda()],
+            ExecutionBackend::CoreMl => vec![ExecutionProvider::coreml()],
+            ExecutionBackend::DirectMl => vec![ExecutionProvider::directml()],
+            ExecutionBackend::Auto => vec![
+                ExecutionProvider::cuda(),
+                ExecutionProvider::coreml(),
+                ExecutionProvider::directml(),
+            ],
+        };
+        providers.push(ExecutionProvider::cpu());
+        providers
+    }
+}
+
+/// Strategy used to collapse per-token hidden states into a single embedding.
+///
+/// Different sentence-transformer checkpoints are trained with different pooling: BERT/BGE-style
+/// models expect the `[CLS]` token, MiniLM-style models expect a mean over tokens. Picking the
+/// wrong one silently degrades embedding quality rather than erroring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Attention-mask-weighted mean over the sequence axis.
+    MeanMasked,
+    /// The hidden state of the first (`[CLS]`) token.
+    Cls,
+    /// Element-wise maximum over the sequence axis.
+    Max,
+}
+
 pub struct Semantic {
     #[allow(dead_code)]
     model: Vec<u8>,
 
-    tokenizer: Arc<tokenizers::Tokenizer>,
+    pub(crate) tokenizer: Arc<tokenizers::Tokenizer>,
     session: Arc<ort::InMemorySession<'static>>,
+    pooling: Pooling,
+    normalize: bool,
+    query_prefix: String,
+    passage_prefix: String,
 }
 
 pub type Embedding = Vec<f32>;
 
 impl Semantic {
-    pub async fn initialize(model: Vec<u8>, tokenizer_data: Vec<u8>) -> Result<Pin<Box<Semantic>>, anyhow::Error> {
+    pub async fn initialize(
+        model: Vec<u8>,
+        tokenizer_data: Vec<u8>,
+        pooling: Pooling,
+        normalize: bool,
+        backend: ExecutionBackend,
+        query_prefix: impl Into<String>,
+        passage_prefix: impl Into<String>,
+    ) -> Result<Pin<Box<Semantic>>, anyhow::Error> {
+        let providers = backend.providers();
+
         let environment = Arc::new(
             Environment::builder()
                 .with_name("Encode")
                 .with_log_level(LoggingLevel::Warning)
-                .with_execution_providers([ExecutionProvider::cpu()])
+                .with_execution_providers(providers.clone())
                 .build()?,
         );
 
@@ -45,14 +108,38 @@ impl Semantic {
             session: SessionBuilder::new(&environment)?
                 .with_optimization_level(GraphOptimizationLevel::Level3)?
                 .with_intra_threads(threads)?
+                .with_execution_providers(providers)?
                 .with_model_from_memory(data_ref)
                 .unwrap()
                 .into(),
+            pooling,
+            normalize,
+            query_prefix: query_prefix.into(),
+            passage_prefix: passage_prefix.into(),
         };
 
         Ok(Box::pin(semantic))
     }
 
+    /// The tokenizer backing this session, exposed so callers (e.g. the chunking subsystem) can
+    /// compute token offsets without duplicating tokenizer construction.
+    pub fn tokenizer(&self) -> &tokenizers::Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Embeds `text` as a search query, prepending the `query_prefix` configured at
+    /// `initialize`. Asymmetric retrieval checkpoints (e.g. BGE, E5) expect queries and indexed
+    /// passages to carry different instruction prefixes; omitting this measurably hurts recall.
+    pub fn embed_query(&self, text: &str) -> anyhow::Result<Embedding> {
+        self.embed(&format!("{}{}", self.query_prefix, text))
+    }
+
+    /// Embeds `text` as an indexed passage, prepending the `passage_prefix` configured at
+    /// `initialize`.
+    pub fn embed_passage(&self, text: &str) -> anyhow::Result<Embedding> {
+        self.embed(&format!("{}{}", self.passage_prefix, text))
+    }
+
     pub fn embed(&self, sequence: &str) -> anyhow::Result<Embedding> {
         let tokenizer_output = self.tokenizer.encode(sequence, true).unwrap();
 
@@ -78,14 +165,222 @@ impl Semantic {
 
         let outputs = self.session.run([
             InputTensor::from_array(inputs_ids_array.into_dyn()),
-            InputTensor::from_array(attention_mask_array.into_dyn()),
+            InputTensor::from_array(attention_mask_array.clone().into_dyn()),
             InputTensor::from_array(token_type_ids_array.into_dyn()),
         ])?;
 
         let output_tensor: OrtOwnedTensor<f32, _> = outputs[0].try_extract().unwrap();
-        let sequence_embedding = &*output_tensor.view();
+        let sequence_embedding = output_tensor
+            .view()
+            .to_owned()
+            .into_dimensionality::<ndarray::Ix3>()?;
+
+        let mut pooled = pool(self.pooling, &sequence_embedding.view(), &attention_mask_array);
+        if self.normalize {
+            normalize_rows(&mut pooled);
+        }
+
+        Ok(pooled.index_axis(Axis(0), 0).to_vec())
+    }
+
+    /// Embeds a batch of sequences in a single session call. Unlike `embed`, the sequences are
+    /// padded to a common length; pooling follows the configured `Pooling` and, for the mean and
+    /// max strategies, uses the attention mask so that padding tokens don't pollute the pooled
+    /// embedding.
+    pub fn embed_batch(&self, sequences: &[&str]) -> anyhow::Result<Vec<Embedding>> {
+        let mut tokenizer = (*self.tokenizer).clone();
+        tokenizer.with_padding(Some(tokenizers::PaddingParams {
+            strategy: tokenizers::PaddingStrategy::BatchLongest,
+            ..Default::default()
+        }));
+
+        let encodings = tokenizer
+            .encode_batch(sequences.to_vec(), true)
+            .map_err(|e| anyhow!("tokenizer encode_batch error: {}", e))?;
+
+        let batch = encodings.len();
+        let length = encodings.first().map(|e| e.get_ids().len()).unwrap_or(0);
+
+        let mut input_ids = Vec::with_capacity(batch * length);
+        let mut attention_mask = Vec::with_capacity(batch * length);
+        let mut token_type_ids = Vec::with_capacity(batch * length);
+
+        for encoding in &encodings {
+            input_ids.extend(encoding.get_ids().iter().map(|&x| x as i64));
+            attention_mask.extend(encoding.get_attention_mask().iter().map(|&x| x as i64));
+            token_type_ids.extend(encoding.get_type_ids().iter().map(|&x| x as i64));
+        }
+
+        let inputs_ids_array = ndarray::Array::from_shape_vec((batch, length), input_ids)?;
+        let attention_mask_array = ndarray::Array::from_shape_vec((batch, length), attention_mask)?;
+        let token_type_ids_array = ndarray::Array::from_shape_vec((batch, length), token_type_ids)?;
+
+        let outputs = self.session.run([
+            InputTensor::from_array(inputs_ids_array.clone().into_dyn()),
+            InputTensor::from_array(attention_mask_array.clone().into_dyn()),
+            InputTensor::from_array(token_type_ids_array.into_dyn()),
+        ])?;
+
+        let output_tensor: OrtOwnedTensor<f32, _> = outputs[0].try_extract().unwrap();
+        let sequence_embedding = output_tensor
+            .view()
+            .to_owned()
+            .into_dimensionality::<ndarray::Ix3>()?;
+
+        let mut pooled = pool(self.pooling, &sequence_embedding.view(), &attention_mask_array);
+        if self.normalize {
+            normalize_rows(&mut pooled);
+        }
+
+        Ok(pooled.outer_iter().map(|row| row.to_vec()).collect())
+    }
+}
+
+/// Collapses per-token hidden states `(batch, seq_len, hidden)` into per-sequence embeddings
+/// `(batch, hidden)` according to the given pooling strategy.
+fn pool(pooling: Pooling, sequence_embedding: &ArrayView3<f32>, attention_mask: &Array2<i64>) -> Array2<f32> {
+    match pooling {
+        Pooling::MeanMasked => masked_mean(sequence_embedding, attention_mask),
+        Pooling::Cls => sequence_embedding.index_axis(Axis(1), 0).to_owned(),
+        Pooling::Max => masked_max(sequence_embedding, attention_mask),
+    }
+}
+
+/// Element-wise maximum over the sequence axis, starting from `f32::MIN` and skipping padding
+/// positions so they can never win the max for a shorter sequence in a padded batch.
+fn masked_max(sequence_embedding: &ArrayView3<f32>, attention_mask: &Array2<i64>) -> Array2<f32> {
+    let (batch, seq_len, hidden) = sequence_embedding.dim();
+    let mut pooled = Array2::<f32>::from_elem((batch, hidden), f32::MIN);
+
+    for b in 0..batch {
+        for s in 0..seq_len {
+            if attention_mask[[b, s]] == 0 {
+                continue;
+            }
+            let token = sequence_embedding.index_axis(Axis(0), b).index_axis(Axis(0), s);
+            for h in 0..hidden {
+                pooled[[b, h]] = pooled[[b, h]].max(token[h]);
+            }
+        }
+    }
+
+    pooled
+}
+
+/// Mean-pools the hidden states along the sequence axis, weighting each token by its attention
+/// mask so that padding positions don't contribute to the average.
+fn masked_mean(sequence_embedding: &ArrayView3<f32>, attention_mask: &Array2<i64>) -> Array2<f32> {
+    let (batch, seq_len, hidden) = sequence_embedding.dim();
+    let mut pooled = Array2::<f32>::zeros((batch, hidden));
+
+    for b in 0..batch {
+        let mut mask_sum = 0f32;
+        for s in 0..seq_len {
+            let mask = attention_mask[[b, s]] as f32;
+            mask_sum += mask;
+            if mask == 0.0 {
+                continue;
+            }
+            let token = sequence_embedding.index_axis(Axis(0), b).index_axis(Axis(0), s);
+            for h in 0..hidden {
+                pooled[[b, h]] += token[h] * mask;
+            }
+        }
+        let denom = mask_sum.max(1e-9);
+        for h in 0..hidden {
+            pooled[[b, h]] /= denom;
+        }
+    }
+
+    pooled
+}
+
+/// Divides each row in place by its L2 norm (clamped to a small epsilon), turning pooled
+/// embeddings into unit vectors so that downstream search can compare them with a plain dot
+/// product instead of a full cosine similarity.
+fn normalize_rows(pooled: &mut Array2<f32>) {
+    for mut row in pooled.outer_iter_mut() {
+        let norm = row.iter().map(|x| x * x).sum::<f32>().sqrt().max(1e-9);
+        row.mapv_inplace(|x| x / norm);
+    }
+}
+
+/// Dot product of two embeddings. If both vectors are unit-normalized (see `normalize` on
+/// `Semantic::initialize`) this is equivalent to their cosine similarity.
+pub fn dot(a: &Embedding, b: &Embedding) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Cosine similarity between two embeddings, robust to either vector not being normalized.
+pub fn cosine_similarity(a: &Embedding, b: &Embedding) -> f32 {
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot(a, b) / (norm_a * norm_b).max(1e-9)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_mean_ignores_padding_tokens() {
+        // One sequence of three tokens with a 1-dim hidden state: the first two are real content,
+        // the last is a padding token whose (huge) hidden state must not leak into the pooled mean.
+        let sequence_embedding =
+            ndarray::array![[[1.0f32], [3.0], [999.0]]];
+        let attention_mask = ndarray::array![[1i64, 1, 0]];
+
+        let pooled = masked_mean(&sequence_embedding.view(), &attention_mask);
+
+        assert_eq!(pooled[[0, 0]], 2.0);
+    }
+
+    #[test]
+    fn plain_mean_is_polluted_by_padding() {
+        // Same inputs as above, but pooled with the unmasked `mean_axis` the original `embed`
+        // used before this fix: padding pollutes the result, which is the bug this series fixes.
+        let sequence_embedding = ndarray::array![[[1.0f32], [3.0], [999.0]]];
+
         let pooled = sequence_embedding.mean_axis(Axis(1)).unwrap();
 
-        Ok(pooled.to_owned().as_slice().unwrap().to_vec())
+        assert_ne!(pooled[[0, 0]], 2.0);
+    }
+
+    #[test]
+    fn normalize_rows_produces_unit_vectors() {
+        let mut pooled = ndarray::array![[3.0f32, 4.0], [0.0, 0.0]];
+
+        normalize_rows(&mut pooled);
+
+        let norm: f32 = pooled.row(0).iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+        assert_eq!(pooled[[0, 0]], 0.6);
+        assert_eq!(pooled[[0, 1]], 0.8);
+        // A zero vector's norm is clamped to an epsilon rather than dividing by zero.
+        assert!(pooled.row(1).iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn dot_sums_elementwise_products() {
+        let a: Embedding = vec![1.0, 2.0, 3.0];
+        let b: Embedding = vec![4.0, 5.0, 6.0];
+
+        assert_eq!(dot(&a, &b), 32.0);
+    }
+
+    #[test]
+    fn cosine_similarity_of_unit_vectors_equals_dot() {
+        let a: Embedding = vec![0.6, 0.8];
+        let b: Embedding = vec![0.8, 0.6];
+
+        assert!((cosine_similarity(&a, &b) - dot(&a, &b)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_is_scale_invariant() {
+        let a: Embedding = vec![1.0, 0.0];
+        let b: Embedding = vec![2.0, 0.0];
+
+        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 1e-6);
     }
 }
@@ -0,0 +1,4 @@
+pub mod chunk;
+pub mod embed;
+pub mod provider;
+pub mod store;
@@ -0,0 +1,140 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::embed::{Embedding, Semantic};
+
+/// Uniform interface over anything that can turn text into embeddings, whether the model runs
+/// locally or is reached over HTTP. Lets the chunking and search subsystems stay agnostic of
+/// where embeddings actually come from.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<Embedding>>;
+    fn dimensions(&self) -> usize;
+    fn max_tokens(&self) -> usize;
+}
+
+/// Wraps a local ONNX `Semantic` session behind the `EmbeddingProvider` interface.
+pub struct LocalOnnxProvider {
+    semantic: Arc<Pin<Box<Semantic>>>,
+    dimensions: usize,
+    max_tokens: usize,
+}
+
+impl LocalOnnxProvider {
+    pub fn new(semantic: Pin<Box<Semantic>>, dimensions: usize, max_tokens: usize) -> Self {
+        Self {
+            semantic: Arc::new(semantic),
+            dimensions,
+            max_tokens,
+        }
+    }
+
+    /// The tokenizer backing the wrapped session, for callers (e.g. `chunk_and_embed`) that need
+    /// token offsets to window a document before embedding it.
+    pub fn tokenizer(&self) -> &tokenizers::Tokenizer {
+        self.semantic.tokenizer()
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LocalOnnxProvider {
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<Embedding>> {
+        // `Semantic::embed_batch` runs the ONNX session synchronously. `spawn_blocking` moves it
+        // to the blocking thread pool instead of `block_in_place`, which would panic under a
+        // current-thread runtime (the default for `#[tokio::test]`); that requires the closure to
+        // be `'static`, so the texts are copied and the session shared via `Arc` rather than
+        // borrowed from `&self`.
+        let semantic = Arc::clone(&self.semantic);
+        let owned_texts: Vec<String> = texts.iter().map(|s| s.to_string()).collect();
+
+        tokio::task::spawn_blocking(move || {
+            let texts: Vec<&str> = owned_texts.iter().map(String::as_str).collect();
+            semantic.embed_batch(&texts)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("embedding task panicked: {}", e))?
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+}
+
+#[derive(serde::Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingsResponseItem>,
+}
+
+#[derive(serde::Deserialize)]
+struct EmbeddingsResponseItem {
+    embedding: Embedding,
+}
+
+/// Talks to an OpenAI-compatible `/embeddings` endpoint (this also covers Ollama, which exposes
+/// the same shape), letting a hosted model stand in for the local ONNX session behind the same
+/// `EmbeddingProvider` interface.
+pub struct RemoteHttpProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+    dimensions: usize,
+    max_tokens: usize,
+}
+
+impl RemoteHttpProvider {
+    pub fn new(
+        endpoint: impl Into<String>,
+        api_key: Option<String>,
+        model: impl Into<String>,
+        dimensions: usize,
+        max_tokens: usize,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+            api_key,
+            model: model.into(),
+            dimensions,
+            max_tokens,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for RemoteHttpProvider {
+    async fn embed_batch(&self, texts: &[&str]) -> anyhow::Result<Vec<Embedding>> {
+        let mut request = self.client.post(&self.endpoint).json(&EmbeddingsRequest {
+            model: &self.model,
+            input: texts,
+        });
+
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: EmbeddingsResponse = request.send().await?.error_for_status()?.json().await?;
+
+        Ok(response.data.into_iter().map(|item| item.embedding).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::chunk::Chunk;
+use crate::embed::{cosine_similarity, Embedding};
+
+/// A single embedded chunk persisted to the vector store, tagged with the file it came from so
+/// a hit can be traced back to its source.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Record {
+    pub id: u64,
+    pub path: String,
+    pub byte_range: Range<usize>,
+    pub embedding: Embedding,
+}
+
+/// A search hit: a stored record plus its similarity score against the query embedding.
+pub struct ScoredHit {
+    pub record: Record,
+    pub score: f32,
+}
+
+/// Append-only, disk-backed index of embedded chunks. `search` always scores by cosine
+/// similarity, so results are correct regardless of whether the caller indexed unit-normalized
+/// vectors (see the `normalize` option on `Semantic::initialize`) or raw ones.
+pub struct VectorStore {
+    path: PathBuf,
+    records: Vec<Record>,
+    next_id: u64,
+}
+
+impl VectorStore {
+    /// Opens (or creates) the store backed by the JSON-lines file at `path`, loading any
+    /// previously persisted records into memory.
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let mut records = Vec::new();
+
+        if path.exists() {
+            let file = File::open(&path)?;
+            for line in BufReader::new(file).lines() {
+                records.push(serde_json::from_str(&line?)?);
+            }
+        }
+
+        let next_id = records.iter().map(|r: &Record| r.id + 1).max().unwrap_or(0);
+
+        Ok(Self {
+            path,
+            records,
+            next_id,
+        })
+    }
+
+    /// Appends `chunks` (tagged with the file they came from) to the store, persisting them to
+    /// disk immediately.
+    pub fn add(&mut self, source_path: &str, chunks: &[Chunk]) -> anyhow::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+
+        for chunk in chunks {
+            let record = Record {
+                id: self.next_id,
+                path: source_path.to_string(),
+                byte_range: chunk.byte_range.clone(),
+                embedding: chunk.embedding.clone(),
+            };
+            self.next_id += 1;
+
+            writeln!(file, "{}", serde_json::to_string(&record)?)?;
+            self.records.push(record);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `k` records whose embeddings have the highest cosine similarity to
+    /// `query_embedding`, highest first.
+    pub fn search(&self, query_embedding: &Embedding, k: usize) -> Vec<ScoredHit> {
+        let mut scored: Vec<ScoredHit> = self
+            .records
+            .iter()
+            .map(|record| ScoredHit {
+                record: record.clone(),
+                score: cosine_similarity(&record.embedding, query_embedding),
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(k);
+        scored
+    }
+}